@@ -1,26 +1,55 @@
-use anyhow::{Context, Result};
-use diretto::{
-    ClientCapability, Connector, Device as DrmDevice, ModeType, sys::DRM_MODE_OBJECT_PLANE,
-};
-use rustix::{
-    fd::{AsFd, AsRawFd},
-    fs::{Mode, OFlags, open},
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
+
+use anyhow::{Context, Result};
+use diretto::{Connector, Device as DrmDevice, ModeType, sys::DRM_MODE_OBJECT_PLANE};
+use rustix::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd};
 use tracing::{debug, trace, warn};
 use wgpu::{Backends, PresentMode, SurfaceTargetUnsafe};
 
+use crate::{
+    cursor::{self, CursorPlane},
+    session::Session,
+    udev,
+};
+
+/// The DRM-side configuration of a single output: a connected
+/// connector, the mode driving it, and the CRTC/plane pair allocated to
+/// it.
 #[derive(Debug)]
-struct DrmState {
-    device: DrmDevice,
+struct Output {
     connector: Connector,
     mode: diretto::Mode,
     plane_id: u32,
+    crtc_id: u32,
+    /// The CRTC's dedicated cursor plane, if it has one. Not every CRTC
+    /// exposes a cursor plane, so its absence isn't fatal.
+    cursor_plane_id: Option<u32>,
+}
+
+struct DrmState {
+    session: Arc<dyn Session>,
+    device: DrmDevice,
+    devnode: PathBuf,
+    devnum: u64,
+    outputs: Vec<Output>,
     has_master: bool,
 }
 
+/// The wgpu surface backing a single [`Output`], kept at the same index
+/// as its `DrmState::outputs` entry. The surface is configured once when
+/// created; its configuration is not kept.
 #[derive(Debug)]
-struct WgpuState<'s> {
+struct OutputSurface<'s> {
     surface: wgpu::Surface<'s>,
+}
+
+#[derive(Debug)]
+struct WgpuState<'s> {
+    outputs: Vec<OutputSurface<'s>>,
     instance: wgpu::Instance,
     adapter: wgpu::Adapter,
     device: wgpu::Device,
@@ -30,111 +59,216 @@ struct WgpuState<'s> {
 pub struct WgpuContext<'s> {
     drm_state: DrmState,
     wgpu_state: Option<WgpuState<'s>>,
+    cursor: Option<CursorPlane>,
 }
 
-fn open_drm_device() -> Result<DrmDevice> {
-    let fd = open(
-        "/dev/dri/card1",
-        OFlags::RDWR | OFlags::NONBLOCK | OFlags::CLOEXEC,
-        Mode::empty(),
-    )?;
+/// Opens a DRM device through `session`, either the explicit `path` or,
+/// if none was given, whichever card udev's `primary_gpu` heuristic
+/// picks. Returns the device along with its devnode and `dev_t` so
+/// later hotplug code can match udev events against it.
+async fn open_drm_device(
+    session: &dyn Session,
+    path: Option<&Path>,
+) -> Result<(DrmDevice, PathBuf, u64)> {
+    let (devnode, devnum) = match path {
+        Some(path) => {
+            let devnum = rustix::fs::stat(path)
+                .with_context(|| format!("Failed to stat {}", path.display()))?
+                .st_rdev;
+            (path.to_path_buf(), devnum)
+        }
+        None => {
+            let node = udev::primary_gpu().context("Failed to find a GPU via udev")?;
+            (node.devnode, node.devnum)
+        }
+    };
+
+    let raw_fd = session
+        .open_device(&devnode)
+        .await
+        .with_context(|| format!("Failed to open {}", devnode.display()))?;
+    let fd = unsafe { OwnedFd::from_raw_fd(raw_fd) };
     let device = unsafe { DrmDevice::new_unchecked(fd) };
 
-    debug!("Opened DRM device /dev/dri/card1");
-    Ok(device)
+    debug!("Opened DRM device {}", devnode.display());
+    Ok((device, devnode, devnum))
 }
 
-fn setup_drm_master(device: &DrmDevice) -> Result<()> {
-    device.set_master().context("Failed to become DRM master")?;
-    device
-        .set_client_capability(ClientCapability::Atomic, true)
-        .context("Failed to set atomic capability")?;
-    debug!("Acquired DRM master status");
-    Ok(())
+/// Picks the connector's first mode flagged `DEFAULT`, falling back to
+/// the largest by area if none is.
+fn select_mode(connector: &Connector) -> Result<diretto::Mode> {
+    let mut best_mode = None;
+    let mut max_area = 0;
+
+    for current_mode in connector.modes.iter().copied() {
+        if current_mode.ty().contains(ModeType::DEFAULT) {
+            best_mode = Some(current_mode);
+            break;
+        }
+
+        let area = current_mode.display_width() as u32 * current_mode.display_height() as u32;
+        if area > max_area {
+            best_mode = Some(current_mode);
+            max_area = area;
+        }
+    }
+
+    best_mode.ok_or_else(|| anyhow::anyhow!("No suitable mode found"))
 }
 
-fn release_drm_master(device: &DrmDevice) -> Result<()> {
-    device.drop_master().context("Failed to drop DRM master")?;
-    debug!("Released DRM master status");
-    Ok(())
+/// Finds a CRTC the given connector can drive and that isn't already
+/// claimed by another connector, by intersecting each of the
+/// connector's encoders' `possible_crtcs` bitmask against the device's
+/// CRTC list.
+fn select_crtc(
+    device: &DrmDevice,
+    resources: &diretto::Resources,
+    connector: &Connector,
+    used_crtcs: &mut HashSet<u32>,
+) -> Result<u32> {
+    for encoder_id in &connector.encoders {
+        let encoder_info = device.get_encoder(*encoder_id)?;
+
+        for (index, crtc_id) in resources.crtcs.iter().enumerate() {
+            let possible = encoder_info.possible_crtcs & (1 << index) != 0;
+
+            if possible && !used_crtcs.contains(crtc_id) {
+                trace!(
+                    "Allocated CRTC {} to connector {} via encoder {}",
+                    crtc_id, connector.connector_id, encoder_id
+                );
+                used_crtcs.insert(*crtc_id);
+                return Ok(*crtc_id);
+            }
+        }
+    }
+
+    anyhow::bail!(
+        "No free CRTC available for connector {}",
+        connector.connector_id
+    )
 }
 
-fn setup_drm_resources(device: &DrmDevice) -> Result<(Connector, diretto::Mode, u32)> {
-    let resources = device.get_resources()?;
+/// Finds a plane of the given DRM plane `type` (1 = primary, 2 =
+/// cursor) that can be driven by `crtc_id` and isn't already claimed by
+/// another output.
+fn find_plane(
+    device: &DrmDevice,
+    resources: &diretto::Resources,
+    crtc_id: u32,
+    plane_type: u64,
+    used_planes: &mut HashSet<u32>,
+) -> Result<u32> {
+    let crtc_index = resources
+        .crtcs
+        .iter()
+        .position(|id| *id == crtc_id)
+        .context("Allocated CRTC not found in resources")?;
+
+    for id in device.get_plane_resources()? {
+        if used_planes.contains(&id) {
+            continue;
+        }
 
-    // Find connected connector
-    let connector = {
-        let mut found_connector = None;
-        for connector_id in &resources.connectors {
-            let connector = device.get_connector(*connector_id, false)?;
-            if connector.connection.is_connected() {
-                found_connector = Some(connector);
-                break;
+        let plane_info = device.get_plane(id)?;
+        if plane_info.possible_crtcs & (1 << crtc_index) == 0 {
+            continue;
+        }
+
+        let (props, values) = unsafe { device.get_properties(id, DRM_MODE_OBJECT_PLANE)? };
+
+        for (index, prop) in props.into_iter().enumerate() {
+            let (name, _) = unsafe { device.get_property(prop)? };
+
+            if name.as_c_str() == c"type" && values[index] == plane_type {
+                trace!("Found plane {} (type {}) for CRTC {}", id, plane_type, crtc_id);
+                used_planes.insert(id);
+                return Ok(id);
             }
         }
-        found_connector.ok_or_else(|| anyhow::anyhow!("No connected display found"))?
-    };
+    }
+
+    anyhow::bail!("No suitable plane found for CRTC {crtc_id}")
+}
 
-    // Find best mode
-    let mode = {
-        let mut best_mode = None;
-        let mut max_area = 0;
+/// Discovers every connected connector and allocates it a distinct CRTC
+/// and primary plane, so every attached display gets driven rather than
+/// just the first one found.
+fn setup_outputs(device: &DrmDevice) -> Result<Vec<Output>> {
+    let resources = device.get_resources()?;
+    let mut used_crtcs = HashSet::new();
+    let mut used_planes = HashSet::new();
+    let mut outputs = Vec::new();
+
+    for connector_id in &resources.connectors {
+        let connector = device.get_connector(*connector_id, false)?;
+        if !connector.connection.is_connected() {
+            continue;
+        }
 
-        for current_mode in connector.modes.iter().copied() {
-            if current_mode.ty().contains(ModeType::DEFAULT) {
-                best_mode = Some(current_mode);
-                break;
+        let mode = match select_mode(&connector) {
+            Ok(mode) => mode,
+            Err(e) => {
+                warn!("Skipping connector {}: {e}", connector.connector_id);
+                continue;
             }
+        };
 
-            let area = current_mode.display_width() as u32 * current_mode.display_height() as u32;
-            if area > max_area {
-                best_mode = Some(current_mode);
-                max_area = area;
+        let crtc_id = match select_crtc(device, &resources, &connector, &mut used_crtcs) {
+            Ok(id) => id,
+            Err(e) => {
+                warn!("Skipping connector {}: {e}", connector.connector_id);
+                continue;
             }
-        }
-        best_mode.ok_or_else(|| anyhow::anyhow!("No suitable mode found"))?
-    };
+        };
 
-    debug!(
-        "Selected mode {}x{}@{}",
-        mode.display_width(),
-        mode.display_height(),
-        mode.vertical_refresh_rate()
-    );
-
-    // Find primary plane
-    let plane_id = {
-        let plane_resources = device.get_plane_resources()?;
-        let mut primary_plane = None;
-
-        for id in plane_resources {
-            let (props, values) = unsafe { device.get_properties(id, DRM_MODE_OBJECT_PLANE)? };
-
-            for (index, prop) in props.into_iter().enumerate() {
-                let (name, _) = unsafe { device.get_property(prop)? };
-                let current_value = values[index];
-
-                if name.as_c_str() == c"type" && current_value == 1 {
-                    trace!("Found primary plane: {}", id);
-                    primary_plane = Some(id);
-                    break;
-                }
+        let plane_id = match find_plane(device, &resources, crtc_id, 1, &mut used_planes) {
+            Ok(id) => id,
+            Err(e) => {
+                warn!("Skipping connector {}: {e}", connector.connector_id);
+                continue;
             }
+        };
 
-            if primary_plane.is_some() {
-                break;
+        let cursor_plane_id = match find_plane(device, &resources, crtc_id, 2, &mut used_planes) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                debug!("No cursor plane for CRTC {crtc_id}: {e}");
+                None
             }
-        }
-        primary_plane.ok_or_else(|| anyhow::anyhow!("No primary plane found"))?
-    };
+        };
+
+        debug!(
+            "Configured output on connector {}: {}x{}@{}",
+            connector.connector_id,
+            mode.display_width(),
+            mode.display_height(),
+            mode.vertical_refresh_rate()
+        );
+
+        outputs.push(Output {
+            connector,
+            mode,
+            plane_id,
+            crtc_id,
+            cursor_plane_id,
+        });
+    }
+
+    if outputs.is_empty() {
+        anyhow::bail!("No connected display found");
+    }
 
-    Ok((connector, mode, plane_id))
+    Ok(outputs)
 }
 
 impl Drop for DrmState {
     fn drop(&mut self) {
+        // Best-effort synchronous cleanup: Drop can't await anything, but
+        // `Session::release_master` is a plain sync call so it can still
+        // run here as a fallback if `suspend` wasn't called first.
         if self.has_master {
-            if let Err(e) = release_drm_master(&self.device) {
+            if let Err(e) = self.session.release_master(&self.device) {
                 warn!("Failed to release DRM master on drop: {}", e);
             }
         }
@@ -142,39 +276,47 @@ impl Drop for DrmState {
 }
 
 impl<'s> WgpuContext<'s> {
-    pub async fn new() -> Result<Self> {
-        let device = open_drm_device()?;
-        setup_drm_master(&device)?;
-
-        let (connector, mode, plane_id) = setup_drm_resources(&device)?;
+    /// Creates a new context on `path`, or on whichever GPU udev picks as
+    /// primary if `path` is `None`, acquiring the device through
+    /// `session` so it works both as root and as an unprivileged
+    /// logind session. DRM master is also taken through `session`,
+    /// since the logind backend already gets it implicitly and must not
+    /// have `DRM_IOCTL_SET_MASTER` called on top of that.
+    pub async fn new(session: Arc<dyn Session>, path: Option<&Path>) -> Result<Self> {
+        let (device, devnode, devnum) = open_drm_device(session.as_ref(), path).await?;
+        session.acquire_master(&device)?;
+
+        let outputs = setup_outputs(&device)?;
 
         let drm_state = DrmState {
+            session,
             device,
-            connector,
-            mode,
-            plane_id,
+            devnode,
+            devnum,
+            outputs,
             has_master: true,
         };
 
         let mut context = Self {
             drm_state,
             wgpu_state: None,
+            cursor: None,
         };
 
         context.create_wgpu_resources().await?;
+
+        // Give the hardware cursor plane something to show out of the
+        // box; there's no cursor theme support here, so a default
+        // built-in image stands in for one.
+        let (width, height) = cursor::cursor_size(&context.drm_state.device);
+        if let Err(e) = context.set_cursor(&cursor::default_cursor_image(width, height), (0, 0)) {
+            warn!("Failed to set default cursor image: {e}");
+        }
+
         Ok(context)
     }
 
     async fn create_wgpu_resources(&mut self) -> Result<()> {
-        let surface_target = SurfaceTargetUnsafe::Drm {
-            fd: self.drm_state.device.as_fd().as_raw_fd(),
-            plane: self.drm_state.plane_id,
-            connector_id: self.drm_state.connector.connector_id.into(),
-            width: self.drm_state.mode.display_width() as u32,
-            height: self.drm_state.mode.display_height() as u32,
-            refresh_rate: self.drm_state.mode.vertical_refresh_rate() * 1000,
-        };
-
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: Backends::VULKAN,
             flags: wgpu::InstanceFlags::default()
@@ -190,8 +332,6 @@ impl<'s> WgpuContext<'s> {
             .await
             .context("Failed to find an appropriate adapter")?;
 
-        let surface = unsafe { instance.create_surface_unsafe(surface_target)? };
-
         let (device, queue) = adapter
             .request_device(&wgpu::DeviceDescriptor {
                 label: None,
@@ -203,26 +343,46 @@ impl<'s> WgpuContext<'s> {
             .await
             .context("Failed to create device")?;
 
-        let mut config = surface
-            .get_default_config(
-                &adapter,
-                self.drm_state.mode.display_width().into(),
-                self.drm_state.mode.display_height().into(),
-            )
-            .context("Surface not supported by adapter")?;
+        let mut output_surfaces = Vec::with_capacity(self.drm_state.outputs.len());
+
+        for output in &self.drm_state.outputs {
+            let surface_target = SurfaceTargetUnsafe::Drm {
+                fd: self.drm_state.device.as_fd().as_raw_fd(),
+                plane: output.plane_id,
+                connector_id: output.connector.connector_id.into(),
+                width: output.mode.display_width() as u32,
+                height: output.mode.display_height() as u32,
+                refresh_rate: output.mode.vertical_refresh_rate() * 1000,
+            };
 
-        config.present_mode = PresentMode::AutoVsync;
-        surface.configure(&device, &config);
+            let surface = unsafe { instance.create_surface_unsafe(surface_target)? };
+
+            let mut config = surface
+                .get_default_config(
+                    &adapter,
+                    output.mode.display_width().into(),
+                    output.mode.display_height().into(),
+                )
+                .context("Surface not supported by adapter")?;
+
+            config.present_mode = PresentMode::AutoVsync;
+            surface.configure(&device, &config);
+
+            output_surfaces.push(OutputSurface { surface });
+        }
 
         self.wgpu_state = Some(WgpuState {
-            surface,
+            outputs: output_surfaces,
             instance,
             adapter,
             device,
             queue,
         });
 
-        debug!("Created WGPU resources");
+        debug!(
+            "Created WGPU resources for {} output(s)",
+            self.drm_state.outputs.len()
+        );
         Ok(())
     }
 
@@ -237,7 +397,9 @@ impl<'s> WgpuContext<'s> {
         self.destroy_wgpu_resources();
 
         if self.drm_state.has_master {
-            release_drm_master(&self.drm_state.device)?;
+            self.drm_state
+                .session
+                .release_master(&self.drm_state.device)?;
             self.drm_state.has_master = false;
         }
 
@@ -248,7 +410,9 @@ impl<'s> WgpuContext<'s> {
         debug!("Resuming context");
 
         if !self.drm_state.has_master {
-            setup_drm_master(&self.drm_state.device)?;
+            self.drm_state
+                .session
+                .acquire_master(&self.drm_state.device)?;
             self.drm_state.has_master = true;
         }
 
@@ -263,6 +427,94 @@ impl<'s> WgpuContext<'s> {
         self.drm_state.has_master && self.wgpu_state.is_some()
     }
 
+    /// The `dev_t` of the underlying DRM device, for matching against
+    /// udev hotplug events.
+    pub fn devnum(&self) -> u64 {
+        self.drm_state.devnum
+    }
+
+    /// The resolution of the first configured output, used to clamp an
+    /// accumulated pointer position.
+    pub fn primary_output_size(&self) -> Option<(u32, u32)> {
+        self.drm_state
+            .outputs
+            .first()
+            .map(|output| (output.mode.display_width() as u32, output.mode.display_height() as u32))
+    }
+
+    /// Re-runs connector/mode discovery, for use when udev reports a
+    /// "Changed" event on the active card (typically a connector being
+    /// plugged or unplugged).
+    pub async fn rescan_connectors(&mut self) -> Result<()> {
+        debug!("Rescanning connectors");
+
+        self.drm_state.outputs = setup_outputs(&self.drm_state.device)?;
+
+        // CRTC/plane IDs are reassigned from scratch above, so a cursor
+        // plane allocated against the old layout may now belong to a
+        // different output or no longer exist. Drop it and put the
+        // default image back up, which makes `set_cursor` allocate a
+        // fresh one against the new outputs.
+        self.cursor = None;
+
+        if self.wgpu_state.is_some() {
+            self.destroy_wgpu_resources();
+            self.create_wgpu_resources().await?;
+        }
+
+        let (width, height) = cursor::cursor_size(&self.drm_state.device);
+        if let Err(e) = self.set_cursor(&cursor::default_cursor_image(width, height), (0, 0)) {
+            warn!("Failed to restore default cursor image: {e}");
+        }
+
+        Ok(())
+    }
+
+    /// Uploads `image` (tightly packed ARGB8888) as the hardware cursor
+    /// on the first output exposing a cursor plane, recording `hotspot`
+    /// for later [`WgpuContext::move_cursor`] calls. Does nothing if no
+    /// output has a cursor plane.
+    pub fn set_cursor(&mut self, image: &[u8], hotspot: (i32, i32)) -> Result<()> {
+        let Some(output) = self
+            .drm_state
+            .outputs
+            .iter()
+            .find(|output| output.cursor_plane_id.is_some())
+        else {
+            return Ok(());
+        };
+
+        let cursor_plane_id = output.cursor_plane_id.unwrap();
+        let crtc_id = output.crtc_id;
+
+        if self.cursor.is_none() {
+            let (width, height) = cursor::cursor_size(&self.drm_state.device);
+            self.cursor = Some(CursorPlane::new(
+                &self.drm_state.device,
+                cursor_plane_id,
+                crtc_id,
+                width,
+                height,
+            )?);
+        }
+
+        self.cursor
+            .as_mut()
+            .expect("cursor plane initialized above")
+            .set_image(&self.drm_state.device, image, hotspot)
+    }
+
+    /// Moves the hardware cursor, if one has been set via
+    /// [`WgpuContext::set_cursor`].
+    pub fn move_cursor(&mut self, x: i32, y: i32) -> Result<()> {
+        if let Some(cursor) = self.cursor.as_mut() {
+            cursor.move_to(&self.drm_state.device, x, y)?;
+        }
+
+        Ok(())
+    }
+
+    /// Renders and presents a frame on every configured output.
     pub fn present(&self) -> Result<()> {
         let wgpu_state = self
             .wgpu_state
@@ -273,38 +525,40 @@ impl<'s> WgpuContext<'s> {
             return Err(anyhow::anyhow!("Cannot present: no DRM master status"));
         }
 
-        let frame = wgpu_state
-            .surface
-            .get_current_texture()
-            .context("Failed to acquire next swapchain texture")?;
-
-        let texture_view = frame
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-
-        let mut encoder = wgpu_state
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-
-        let renderpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &texture_view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::GREEN),
-                    store: wgpu::StoreOp::Store,
-                },
-                depth_slice: None,
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
-        });
+        for output in &wgpu_state.outputs {
+            let frame = output
+                .surface
+                .get_current_texture()
+                .context("Failed to acquire next swapchain texture")?;
+
+            let texture_view = frame
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default());
 
-        drop(renderpass);
-        wgpu_state.queue.submit([encoder.finish()]);
-        frame.present();
+            let mut encoder = wgpu_state
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+            let renderpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &texture_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::GREEN),
+                        store: wgpu::StoreOp::Store,
+                    },
+                    depth_slice: None,
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            drop(renderpass);
+            wgpu_state.queue.submit([encoder.finish()]);
+            frame.present();
+        }
 
         Ok(())
     }
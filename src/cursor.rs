@@ -0,0 +1,172 @@
+use std::ffi::CStr;
+
+use anyhow::{Context, Result};
+use diretto::{
+    AtomicCommitFlags, AtomicRequest, Device as DrmDevice,
+    sys::{DRM_CAP_CURSOR_HEIGHT, DRM_CAP_CURSOR_WIDTH, DRM_MODE_OBJECT_PLANE},
+};
+use tracing::{debug, trace};
+
+/// Fallback cursor plane size for drivers that don't report
+/// `DRM_CAP_CURSOR_WIDTH`/`DRM_CAP_CURSOR_HEIGHT` — most hardware
+/// cursor planes cap out around this size anyway.
+const DEFAULT_CURSOR_SIZE: u32 = 64;
+
+/// Queries the driver's maximum cursor plane dimensions, falling back
+/// to [`DEFAULT_CURSOR_SIZE`] if it isn't reported. Cursor planes
+/// enforce a small, fixed maximum size; committing a buffer sized to
+/// the output resolution is rejected by real hardware.
+pub fn cursor_size(device: &DrmDevice) -> (u32, u32) {
+    let width = device
+        .get_cap(DRM_CAP_CURSOR_WIDTH)
+        .map(|cap| cap as u32)
+        .unwrap_or(DEFAULT_CURSOR_SIZE);
+    let height = device
+        .get_cap(DRM_CAP_CURSOR_HEIGHT)
+        .map(|cap| cap as u32)
+        .unwrap_or(DEFAULT_CURSOR_SIZE);
+
+    (width, height)
+}
+
+/// Builds a minimal built-in cursor image (an opaque white square with
+/// a black border) sized `width`x`height`, tightly packed ARGB8888.
+/// Used as the default cursor so the hardware cursor plane shows
+/// something out of the box, since this crate has no cursor theme
+/// support.
+pub fn default_cursor_image(width: u32, height: u32) -> Vec<u8> {
+    let mut image = vec![0u8; (width * height * 4) as usize];
+
+    for y in 0..height {
+        for x in 0..width {
+            let border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            let (b, g, r, a) = if border {
+                (0, 0, 0, 255)
+            } else {
+                (255, 255, 255, 255)
+            };
+
+            let offset = ((y * width + x) * 4) as usize;
+            image[offset] = b;
+            image[offset + 1] = g;
+            image[offset + 2] = r;
+            image[offset + 3] = a;
+        }
+    }
+
+    image
+}
+
+fn find_property_id(device: &DrmDevice, object_id: u32, object_type: u32, name: &CStr) -> Result<u32> {
+    let (props, _) = unsafe { device.get_properties(object_id, object_type)? };
+
+    for prop in props {
+        let (prop_name, _) = unsafe { device.get_property(prop)? };
+        if prop_name.as_c_str() == name {
+            return Ok(prop);
+        }
+    }
+
+    anyhow::bail!("Property {name:?} not found on object {object_id}")
+}
+
+/// A dumb-buffer-backed cursor image bound to a CRTC's cursor plane,
+/// updated through atomic property commits independent of the main
+/// render surface, so moving the pointer never has to wait on a frame.
+#[derive(Debug)]
+pub struct CursorPlane {
+    plane_id: u32,
+    crtc_id: u32,
+    handle: u32,
+    fb_id: u32,
+    width: u32,
+    height: u32,
+    hot_x: i32,
+    hot_y: i32,
+}
+
+impl CursorPlane {
+    /// Allocates a dumb buffer sized `width`x`height` for `plane_id` on
+    /// `crtc_id`.
+    pub fn new(device: &DrmDevice, plane_id: u32, crtc_id: u32, width: u32, height: u32) -> Result<Self> {
+        let dumb_buffer = device
+            .create_dumb_buffer(width, height, 32)
+            .context("Failed to create dumb buffer for cursor")?;
+
+        let fb_id = device
+            .add_framebuffer(width, height, 32, 32, dumb_buffer.pitch, dumb_buffer.handle)
+            .context("Failed to add framebuffer for cursor")?;
+
+        debug!(
+            "Allocated {}x{} cursor plane {} on CRTC {}",
+            width, height, plane_id, crtc_id
+        );
+
+        Ok(Self {
+            plane_id,
+            crtc_id,
+            handle: dumb_buffer.handle,
+            fb_id,
+            width,
+            height,
+            hot_x: 0,
+            hot_y: 0,
+        })
+    }
+
+    /// Uploads a new cursor image (tightly packed ARGB8888, `width`x`height`
+    /// as given to [`CursorPlane::new`]) and records `hotspot` for
+    /// subsequent [`CursorPlane::move_to`] calls.
+    pub fn set_image(&mut self, device: &DrmDevice, image: &[u8], hotspot: (i32, i32)) -> Result<()> {
+        let mut mapping = unsafe { device.map_dumb_buffer(self.handle, self.width, self.height) }
+            .context("Failed to map cursor dumb buffer")?;
+
+        let len = image.len().min(mapping.len());
+        mapping[..len].copy_from_slice(&image[..len]);
+
+        self.hot_x = hotspot.0;
+        self.hot_y = hotspot.1;
+
+        self.commit(device, -self.hot_x, -self.hot_y)
+    }
+
+    /// Moves the cursor plane so the hotspot lands on `(x, y)`.
+    pub fn move_to(&mut self, device: &DrmDevice, x: i32, y: i32) -> Result<()> {
+        self.commit(device, x - self.hot_x, y - self.hot_y)
+    }
+
+    fn commit(&self, device: &DrmDevice, x: i32, y: i32) -> Result<()> {
+        let crtc_id_prop = find_property_id(device, self.plane_id, DRM_MODE_OBJECT_PLANE, c"CRTC_ID")?;
+        let fb_id_prop = find_property_id(device, self.plane_id, DRM_MODE_OBJECT_PLANE, c"FB_ID")?;
+        let crtc_x_prop = find_property_id(device, self.plane_id, DRM_MODE_OBJECT_PLANE, c"CRTC_X")?;
+        let crtc_y_prop = find_property_id(device, self.plane_id, DRM_MODE_OBJECT_PLANE, c"CRTC_Y")?;
+        let crtc_w_prop = find_property_id(device, self.plane_id, DRM_MODE_OBJECT_PLANE, c"CRTC_W")?;
+        let crtc_h_prop = find_property_id(device, self.plane_id, DRM_MODE_OBJECT_PLANE, c"CRTC_H")?;
+        let src_x_prop = find_property_id(device, self.plane_id, DRM_MODE_OBJECT_PLANE, c"SRC_X")?;
+        let src_y_prop = find_property_id(device, self.plane_id, DRM_MODE_OBJECT_PLANE, c"SRC_Y")?;
+        let src_w_prop = find_property_id(device, self.plane_id, DRM_MODE_OBJECT_PLANE, c"SRC_W")?;
+        let src_h_prop = find_property_id(device, self.plane_id, DRM_MODE_OBJECT_PLANE, c"SRC_H")?;
+
+        let mut request = AtomicRequest::new();
+        request.add_property(self.plane_id, crtc_id_prop, self.crtc_id as u64);
+        request.add_property(self.plane_id, fb_id_prop, self.fb_id as u64);
+        request.add_property(self.plane_id, crtc_x_prop, x as u64);
+        request.add_property(self.plane_id, crtc_y_prop, y as u64);
+        request.add_property(self.plane_id, crtc_w_prop, self.width as u64);
+        request.add_property(self.plane_id, crtc_h_prop, self.height as u64);
+        // The source rectangle is in 16.16 fixed-point and mandatory on
+        // every atomic plane update; the cursor always samples the
+        // whole buffer starting at its origin.
+        request.add_property(self.plane_id, src_x_prop, 0);
+        request.add_property(self.plane_id, src_y_prop, 0);
+        request.add_property(self.plane_id, src_w_prop, (self.width as u64) << 16);
+        request.add_property(self.plane_id, src_h_prop, (self.height as u64) << 16);
+
+        device
+            .atomic_commit(&request, AtomicCommitFlags::empty())
+            .context("Failed to commit cursor plane update")?;
+
+        trace!("Moved cursor plane {} to ({}, {})", self.plane_id, x, y);
+        Ok(())
+    }
+}
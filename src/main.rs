@@ -1,18 +1,17 @@
 use std::{
     collections::{HashMap, HashSet},
     ffi::CString,
-    os::fd::{BorrowedFd, IntoRawFd},
     process::exit,
     sync::Arc,
     time::Duration,
 };
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use colpetto::{
     event::KeyState,
     helper::{
         Handle as LibinputHandle,
-        event::{EventType, KeyboardEvent},
+        event::{EventType, KeyboardEvent, PointerEvent},
     },
 };
 use context::WgpuContext;
@@ -20,7 +19,7 @@ use input_linux_sys::{
     KEY_ESC, KEY_F1, KEY_F2, KEY_F3, KEY_F4, KEY_F5, KEY_F6, KEY_F7, KEY_F8, KEY_F9, KEY_LEFTALT,
     KEY_LEFTCTRL, KEY_RIGHTALT, KEY_RIGHTCTRL,
 };
-use saddle::Seat;
+use session::{DirectSession, LogindSession, Session};
 use tokio::{
     pin,
     sync::{RwLock, mpsc, watch},
@@ -30,6 +29,22 @@ use tokio_stream::{StreamExt, wrappers::WatchStream};
 use tracing::{debug, error, info};
 
 mod context;
+mod cursor;
+mod session;
+mod udev;
+
+/// Picks a direct/seatd session when running as root, and a logind
+/// session otherwise, so the program works for both a privileged
+/// compositor and an unprivileged desktop session.
+async fn open_session() -> Result<Arc<dyn Session>> {
+    if rustix::process::geteuid().is_root() {
+        info!("Running as root, using the direct session backend");
+        Ok(Arc::new(DirectSession::new().await?))
+    } else {
+        info!("Running unprivileged, using the logind session backend");
+        Ok(Arc::new(LogindSession::new().await?))
+    }
+}
 
 /// Maps function keys to VT numbers
 struct KeyMap {
@@ -96,6 +111,59 @@ impl ModifierState {
     }
 }
 
+/// Tracks an accumulated pointer position, clamped to the active
+/// output's resolution.
+struct PointerState {
+    x: f64,
+    y: f64,
+    bounds: (u32, u32),
+}
+
+impl PointerState {
+    fn new() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            bounds: (0, 0),
+        }
+    }
+
+    fn set_bounds(&mut self, bounds: (u32, u32)) {
+        self.bounds = bounds;
+        self.clamp();
+    }
+
+    fn move_relative(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+        self.clamp();
+    }
+
+    fn move_absolute(&mut self, x: f64, y: f64) {
+        self.x = x;
+        self.y = y;
+        self.clamp();
+    }
+
+    fn clamp(&mut self) {
+        self.x = self.x.clamp(0.0, self.bounds.0 as f64);
+        self.y = self.y.clamp(0.0, self.bounds.1 as f64);
+    }
+
+    fn position(&self) -> (i32, i32) {
+        (self.x as i32, self.y as i32)
+    }
+}
+
+/// A pointer event published from the input task to the render loop, so
+/// it can drive the hardware cursor and (eventually) scene input.
+#[derive(Debug, Clone, Copy)]
+enum PointerAction {
+    Motion { x: i32, y: i32 },
+    Button { button: u32, state: KeyState },
+    Scroll { vertical: f64, horizontal: f64 },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     tracing_subscriber::fmt::init();
@@ -105,20 +173,20 @@ async fn main() -> Result<()> {
         exit(-1)
     });
 
-    let seat = Seat::new().await?;
-    let seat_name = CString::new(seat.seat_name()).expect("Invalid seat name");
+    let session = open_session().await?;
+    let seat_name = CString::new(session.seat_name()).expect("Invalid seat name");
 
     let (libinput_handle, mut event_stream) = {
-        let open_seat = seat.clone();
-        let close_seat = seat.clone();
+        let open_session = session.clone();
+        let close_session = session.clone();
 
         LibinputHandle::new(
             move |path| {
-                let seat = open_seat.clone();
+                let session = open_session.clone();
 
                 async move {
-                    match seat.open_device(path).await {
-                        Ok(fd) => fd.into_raw_fd(),
+                    match session.open_device(path).await {
+                        Ok(fd) => fd,
                         Err(err) => {
                             error!("Failed to open device: {err}");
                             -1
@@ -127,10 +195,12 @@ async fn main() -> Result<()> {
                 }
             },
             move |fd| {
-                let seat = close_seat.clone();
+                let session = close_session.clone();
 
                 async move {
-                    let _ = seat.close_device(unsafe { BorrowedFd::borrow_raw(fd) });
+                    if let Err(err) = session.close_device(fd) {
+                        error!("Failed to close device: {err}");
+                    }
                 }
             },
             seat_name,
@@ -139,24 +209,26 @@ async fn main() -> Result<()> {
 
     let key_map = KeyMap::new();
     let modifier_state = Arc::new(RwLock::new(ModifierState::new()));
+    let pointer_state = Arc::new(RwLock::new(PointerState::new()));
+    let (pointer_sx, mut pointer_rx) = mpsc::unbounded_channel::<PointerAction>();
 
     let (control_sx, control_rx) = watch::channel::<bool>(false);
     let libinput_control_rx = control_sx.subscribe();
 
     tokio::spawn({
-        let seat = seat.clone();
+        let session = session.clone();
         let libinput_handle = libinput_handle.clone();
         let modifier_state = modifier_state.clone();
 
         async move {
-            let stream = seat.active_stream().await;
+            let stream = session.activation_stream().await?;
 
             pin!(stream);
 
             while let Some(is_active) = stream.try_next().await? {
                 if is_active {
                     info!("Session became active, taking control");
-                    seat.aquire_session().await?;
+                    session.become_active().await?;
                     control_sx.send(true)?;
 
                     // Reset modifier state when session becomes active to avoid stuck keys
@@ -164,7 +236,7 @@ async fn main() -> Result<()> {
                     libinput_handle.resume()?;
                 } else {
                     info!("Session became inactive");
-                    seat.release_session().await?;
+                    session.release().await?;
                     control_sx.send(false)?;
                     libinput_handle.suspend()?;
                 }
@@ -177,7 +249,8 @@ async fn main() -> Result<()> {
     let (exit_sx, mut exit_rx) = mpsc::unbounded_channel();
 
     tokio::spawn({
-        let seat = seat.clone();
+        let session = session.clone();
+        let pointer_state = pointer_state.clone();
 
         async move {
             let mut has_control = false;
@@ -207,7 +280,7 @@ async fn main() -> Result<()> {
                                                 if has_control {
                                                     info!("Ctrl+Alt+F{vt} pressed, switching to VT {vt}");
 
-                                                    if let Err(e) = seat.switch_session(vt).await {
+                                                    if let Err(e) = session.switch_vt(vt).await {
                                                         error!("Failed to switch to VT {vt}: {e}");
                                                     }
                                                 } else {
@@ -217,6 +290,39 @@ async fn main() -> Result<()> {
                                         }
                                     }
                                 }
+                                EventType::Pointer(PointerEvent::Motion { dx, dy, .. }) => {
+                                    let position = {
+                                        let mut pointer_state = pointer_state.write().await;
+                                        pointer_state.move_relative(dx, dy);
+                                        pointer_state.position()
+                                    };
+
+                                    let (x, y) = position;
+                                    let _ = pointer_sx.send(PointerAction::Motion { x, y });
+                                }
+                                EventType::Pointer(PointerEvent::MotionAbsolute { x, y, .. }) => {
+                                    let position = {
+                                        let mut pointer_state = pointer_state.write().await;
+                                        pointer_state.move_absolute(x, y);
+                                        pointer_state.position()
+                                    };
+
+                                    let (x, y) = position;
+                                    let _ = pointer_sx.send(PointerAction::Motion { x, y });
+                                }
+                                EventType::Pointer(PointerEvent::Button { button, state, .. }) => {
+                                    let _ = pointer_sx.send(PointerAction::Button { button, state });
+                                }
+                                EventType::Pointer(PointerEvent::Axis {
+                                    vertical,
+                                    horizontal,
+                                    ..
+                                }) => {
+                                    let _ = pointer_sx.send(PointerAction::Scroll {
+                                        vertical,
+                                        horizontal,
+                                    });
+                                }
                                 _ => {}
                             },
                             Err(_) => break,
@@ -230,6 +336,7 @@ async fn main() -> Result<()> {
     let mut has_control = false;
     let mut control_stream = WatchStream::new(control_rx);
     let mut render_context = None;
+    let mut udev_events = udev::spawn_monitor().context("Failed to start udev monitor")?;
 
     loop {
         tokio::select! {
@@ -241,13 +348,72 @@ async fn main() -> Result<()> {
             Some(control) = control_stream.next() => {
                 has_control = control;
             }
+            Some(event) = udev_events.recv() => {
+                if let Some(ref mut context) = render_context {
+                    if event.devnum == context.devnum() {
+                        match event.kind {
+                            udev::DrmEventKind::Changed => {
+                                info!("DRM connector change detected, rescanning");
+                                if let Err(e) = context.rescan_connectors().await {
+                                    error!("Failed to rescan connectors: {e}");
+                                }
+
+                                if let Some(bounds) = context.primary_output_size() {
+                                    pointer_state.write().await.set_bounds(bounds);
+                                }
+                            }
+                            udev::DrmEventKind::Removed => {
+                                info!("Active GPU removed, tearing down rendering context");
+                                render_context = None;
+                            }
+                            udev::DrmEventKind::Added => {}
+                        }
+                    }
+                }
+            }
+            Some(action) = pointer_rx.recv() => {
+                // The channel can back up faster than a vsync-gated
+                // render loop drains it, so drain it fully here and
+                // only act on the latest queued motion, rather than
+                // rendering the cursor one stale position per frame.
+                let mut latest_motion = None;
+                let mut next_action = Some(action);
+
+                while let Some(action) = next_action.take() {
+                    match action {
+                        PointerAction::Motion { x, y } => latest_motion = Some((x, y)),
+                        PointerAction::Button { button, state } => {
+                            debug!("Pointer button {button} {state:?}");
+                        }
+                        PointerAction::Scroll { vertical, horizontal } => {
+                            debug!("Pointer scroll vertical={vertical} horizontal={horizontal}");
+                        }
+                    }
+
+                    next_action = pointer_rx.try_recv().ok();
+                }
+
+                if let Some((x, y)) = latest_motion {
+                    if let Some(ref mut context) = render_context {
+                        if let Err(e) = context.move_cursor(x, y) {
+                            error!("Failed to move cursor: {e}");
+                        }
+                    }
+                }
+            }
             else => {}  // No control changes
         };
 
         if has_control {
             if render_context.is_none() {
                 info!("Creating rendering context");
-                render_context = Some(WgpuContext::new().await?);
+                let context = WgpuContext::new(session.clone(), None).await?;
+
+                if let Some(bounds) = context.primary_output_size() {
+                    pointer_state.write().await.set_bounds(bounds);
+                }
+
+                render_context = Some(context);
             }
         } else if render_context.is_some() {
             info!("Dropping rendering context");
@@ -0,0 +1,78 @@
+use std::{
+    os::fd::{BorrowedFd, IntoRawFd, RawFd},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use diretto::{ClientCapability, Device as DrmDevice};
+use saddle::Seat;
+use tokio_stream::StreamExt;
+
+use super::{ActivationStream, BoxFuture, Session};
+
+/// Direct/seatd backend: does the raw DRM master and VT ioctls itself
+/// via [`saddle::Seat`], for running as root (or under seatd) without
+/// logind.
+#[derive(Clone)]
+pub struct DirectSession {
+    seat: Seat,
+}
+
+impl DirectSession {
+    pub async fn new() -> Result<Self> {
+        Ok(Self {
+            seat: Seat::new().await?,
+        })
+    }
+}
+
+impl Session for DirectSession {
+    fn seat_name(&self) -> String {
+        self.seat.seat_name()
+    }
+
+    fn switch_vt(&self, vt: u32) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { self.seat.switch_session(vt).await.map_err(Into::into) })
+    }
+
+    fn open_device<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<RawFd>> {
+        Box::pin(async move {
+            let fd = self.seat.open_device(path).await?;
+            Ok(fd.into_raw_fd())
+        })
+    }
+
+    fn close_device(&self, fd: RawFd) -> Result<()> {
+        self.seat
+            .close_device(unsafe { BorrowedFd::borrow_raw(fd) })?;
+        Ok(())
+    }
+
+    fn become_active(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { self.seat.aquire_session().await.map_err(Into::into) })
+    }
+
+    fn release(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { self.seat.release_session().await.map_err(Into::into) })
+    }
+
+    fn activation_stream(&self) -> BoxFuture<'_, Result<ActivationStream>> {
+        Box::pin(async move {
+            let stream = self.seat.active_stream().await;
+            Ok(Box::pin(stream.map(|result| result.map_err(Into::into))) as ActivationStream)
+        })
+    }
+
+    fn acquire_master(&self, device: &DrmDevice) -> Result<()> {
+        device.set_master().context("Failed to become DRM master")?;
+        device
+            .set_client_capability(ClientCapability::Atomic, true)
+            .context("Failed to set atomic capability")?;
+        Ok(())
+    }
+
+    fn release_master(&self, device: &DrmDevice) -> Result<()> {
+        device.drop_master().context("Failed to drop DRM master")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use std::{
+    os::fd::{IntoRawFd, RawFd},
+    path::Path,
+    pin::Pin,
+};
+
+use anyhow::{Context, Result};
+use rustix::fd::{FromRawFd, OwnedFd as RustixOwnedFd};
+use rustix::fs::{FileType, stat};
+use tokio_stream::{Stream, StreamExt};
+use tracing::debug;
+use zbus::{Connection, proxy, zvariant::OwnedFd};
+
+use super::{ActivationStream, BoxFuture, Session};
+
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1"
+)]
+trait LogindSessionProxy {
+    fn take_control(&self, force: bool) -> zbus::Result<()>;
+    fn release_control(&self) -> zbus::Result<()>;
+    fn take_device(&self, major: u32, minor: u32) -> zbus::Result<(OwnedFd, bool)>;
+    fn release_device(&self, major: u32, minor: u32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn pause_device(&self, major: u32, minor: u32, kind: String) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn resume_device(&self, major: u32, minor: u32, fd: OwnedFd) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LogindManagerProxy {
+    fn get_session_by_pid(&self, pid: u32) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+}
+
+fn major_minor(path: &Path) -> Result<(u32, u32)> {
+    let metadata = stat(path).with_context(|| format!("Failed to stat {}", path.display()))?;
+
+    if FileType::from_raw_mode(metadata.st_mode) != FileType::CharacterDevice {
+        anyhow::bail!("{} is not a character device", path.display());
+    }
+
+    let devnum = metadata.st_rdev;
+    Ok((rustix::fs::major(devnum), rustix::fs::minor(devnum)))
+}
+
+/// logind/D-Bus backend: calls `TakeDevice`/`ReleaseDevice`/`TakeControl`
+/// on the current `org.freedesktop.login1` session and listens for
+/// `PauseDevice`/`ResumeDevice` signals, for running as an unprivileged
+/// user under a logind seat.
+pub struct LogindSession {
+    proxy: LogindSessionProxyProxy<'static>,
+}
+
+impl LogindSession {
+    pub async fn new() -> Result<Self> {
+        let connection = Connection::system()
+            .await
+            .context("Failed to connect to the system bus")?;
+
+        let manager = LogindManagerProxyProxy::new(&connection)
+            .await
+            .context("Failed to create logind manager proxy")?;
+
+        let session_path = manager
+            .get_session_by_pid(0)
+            .await
+            .context("Failed to find the current logind session")?;
+
+        let proxy = LogindSessionProxyProxy::builder(&connection)
+            .path(session_path)?
+            .build()
+            .await
+            .context("Failed to create logind session proxy")?;
+
+        Ok(Self { proxy })
+    }
+}
+
+impl Session for LogindSession {
+    /// Always reports `seat0`; the logind session's own seat is not
+    /// looked up.
+    fn seat_name(&self) -> String {
+        "seat0".to_string()
+    }
+
+    fn switch_vt(&self, vt: u32) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            debug!("VT switching is managed by logind, ignoring request to switch to VT {vt}");
+            Ok(())
+        })
+    }
+
+    fn open_device<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<RawFd>> {
+        Box::pin(async move {
+            let (major, minor) = major_minor(path)?;
+            let (fd, inactive) = self
+                .proxy
+                .take_device(major, minor)
+                .await
+                .with_context(|| format!("Failed to take device {}", path.display()))?;
+
+            debug!("Took device {} from logind (inactive: {inactive})", path.display());
+            Ok(fd.into_raw_fd())
+        })
+    }
+
+    fn close_device(&self, fd: RawFd) -> Result<()> {
+        let metadata = rustix::fs::fstat(unsafe { rustix::fd::BorrowedFd::borrow_raw(fd) })?;
+        let (major, minor) = (
+            rustix::fs::major(metadata.st_rdev),
+            rustix::fs::minor(metadata.st_rdev),
+        );
+
+        let proxy = self.proxy.clone();
+        tokio::spawn(async move {
+            if let Err(e) = proxy.release_device(major, minor).await {
+                tracing::warn!("Failed to release device {major}:{minor}: {e}");
+            }
+        });
+
+        // `ReleaseDevice` just tells logind we're done with it; `fd`
+        // itself was handed to us as an owned descriptor by
+        // `open_device` and is ours to close.
+        drop(unsafe { RustixOwnedFd::from_raw_fd(fd) });
+
+        Ok(())
+    }
+
+    fn become_active(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.proxy
+                .take_control(false)
+                .await
+                .context("Failed to take control of the logind session")
+        })
+    }
+
+    fn release(&self) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.proxy
+                .release_control()
+                .await
+                .context("Failed to release control of the logind session")
+        })
+    }
+
+    fn activation_stream(&self) -> BoxFuture<'_, Result<ActivationStream>> {
+        Box::pin(async move {
+            let pause = self
+                .proxy
+                .receive_pause_device()
+                .await
+                .context("Failed to subscribe to PauseDevice")?
+                .filter_map(|signal| match signal.args() {
+                    // "gone" means a single device was removed, not that
+                    // the whole session was switched away from (logind
+                    // pauses every device we hold on a real VT switch) —
+                    // unplugging one input device shouldn't tear down
+                    // the others or the render context.
+                    Ok(args) if args.kind != "gone" => Some(Ok(false)),
+                    Ok(_) => None,
+                    Err(e) => Some(Err(e.into())),
+                });
+
+            let resume = self
+                .proxy
+                .receive_resume_device()
+                .await
+                .context("Failed to subscribe to ResumeDevice")?
+                .map(|_| Ok(true));
+
+            let stream: Pin<Box<dyn Stream<Item = Result<bool>> + Send>> =
+                Box::pin(tokio_stream::StreamExt::merge(pause, resume));
+
+            Ok(stream)
+        })
+    }
+}
@@ -0,0 +1,59 @@
+use std::{future::Future, os::fd::RawFd, path::Path, pin::Pin};
+
+use anyhow::Result;
+use diretto::Device as DrmDevice;
+use tokio_stream::Stream;
+
+mod direct;
+mod logind;
+
+pub use direct::DirectSession;
+pub use logind::LogindSession;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+pub type ActivationStream = Pin<Box<dyn Stream<Item = Result<bool>> + Send>>;
+
+/// Abstracts session/seat acquisition, so neither `WgpuContext` nor the
+/// libinput open/close callbacks need to know whether they're running
+/// as root with direct VT control (`DirectSession`) or as an
+/// unprivileged logind session (`LogindSession`).
+pub trait Session: Send + Sync {
+    /// The seat this session belongs to (e.g. `seat0`), as libinput
+    /// expects it.
+    fn seat_name(&self) -> String;
+
+    /// Opens `path` through the session, returning an owned fd.
+    fn open_device<'a>(&'a self, path: &'a Path) -> BoxFuture<'a, Result<RawFd>>;
+
+    /// Closes a previously opened device fd.
+    fn close_device(&self, fd: RawFd) -> Result<()>;
+
+    /// Takes control of the session.
+    fn become_active(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// Releases control of the session.
+    fn release(&self) -> BoxFuture<'_, Result<()>>;
+
+    /// A stream of session activation changes (`true` = active).
+    fn activation_stream(&self) -> BoxFuture<'_, Result<ActivationStream>>;
+
+    /// Switches the active VT, where supported. Backends for which this
+    /// doesn't apply (e.g. logind managing a non-VT seat) may no-op.
+    fn switch_vt(&self, vt: u32) -> BoxFuture<'_, Result<()>>;
+
+    /// Takes DRM master on `device`, for backends where opening the
+    /// device doesn't already grant it. The logind backend gets master
+    /// for free via `TakeDevice`, so it's a no-op there; the direct
+    /// backend has to ask for it explicitly.
+    fn acquire_master(&self, device: &DrmDevice) -> Result<()> {
+        let _ = device;
+        Ok(())
+    }
+
+    /// Releases DRM master on `device`, undoing
+    /// [`Session::acquire_master`].
+    fn release_master(&self, device: &DrmDevice) -> Result<()> {
+        let _ = device;
+        Ok(())
+    }
+}
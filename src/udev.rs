@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use tokio::{io::unix::AsyncFd, sync::mpsc};
+use tracing::{debug, trace, warn};
+
+/// A KMS-capable DRM node discovered via udev, along with enough
+/// information to open it and later match hotplug events against it.
+#[derive(Debug, Clone)]
+pub struct DrmNode {
+    pub devnode: PathBuf,
+    pub devnum: u64,
+    pub is_boot_vga: bool,
+}
+
+/// Enumerates the KMS-capable DRM nodes (`card*`, as opposed to the
+/// render-only `renderD*` nodes) exposed by udev under the `drm`
+/// subsystem.
+pub fn enumerate_drm_devices() -> Result<Vec<DrmNode>> {
+    let mut enumerator = udev::Enumerator::new().context("Failed to create udev enumerator")?;
+    enumerator
+        .match_subsystem("drm")
+        .context("Failed to match drm subsystem")?;
+
+    let mut nodes = Vec::new();
+
+    for device in enumerator
+        .scan_devices()
+        .context("Failed to scan udev devices")?
+    {
+        let is_card = device
+            .sysname()
+            .to_str()
+            .is_some_and(|name| name.starts_with("card"));
+
+        if !is_card {
+            continue;
+        }
+
+        let (Some(devnode), Some(devnum)) = (device.devnode(), device.devnum()) else {
+            continue;
+        };
+
+        // Smithay's udev backend reads `boot_vga` off the card's
+        // parent PCI device to decide which GPU the firmware lit up.
+        let is_boot_vga = device
+            .parent_with_subsystem("pci")
+            .ok()
+            .flatten()
+            .and_then(|parent| parent.attribute_value("boot_vga"))
+            .and_then(|value| value.to_str())
+            .is_some_and(|value| value.trim() == "1");
+
+        trace!(
+            "Found KMS-capable DRM node {} (boot_vga: {})",
+            devnode.display(),
+            is_boot_vga
+        );
+
+        nodes.push(DrmNode {
+            devnode: devnode.to_path_buf(),
+            devnum,
+            is_boot_vga,
+        });
+    }
+
+    Ok(nodes)
+}
+
+/// Picks a default GPU the way Smithay's `primary_gpu` helper does:
+/// prefer the device whose parent PCI node is marked `boot_vga == 1`,
+/// falling back to the first KMS-capable card if none is marked.
+pub fn primary_gpu() -> Result<DrmNode> {
+    let mut nodes = enumerate_drm_devices()?;
+    let index = nodes
+        .iter()
+        .position(|node| node.is_boot_vga)
+        .unwrap_or(0);
+
+    if index >= nodes.len() {
+        anyhow::bail!("No KMS-capable DRM device found");
+    }
+
+    let node = nodes.swap_remove(index);
+    debug!("Selected primary GPU: {}", node.devnode.display());
+    Ok(node)
+}
+
+/// The udev actions we care about for DRM hotplug, modeled on Smithay's
+/// `udev_backend_bind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrmEventKind {
+    /// A new DRM card appeared.
+    Added,
+    /// A property of an existing card changed, e.g. a connector was
+    /// plugged or unplugged without the devnode itself changing.
+    Changed,
+    /// A DRM card went away, e.g. an eGPU was unplugged.
+    Removed,
+}
+
+impl DrmEventKind {
+    fn from_udev(event_type: udev::EventType) -> Option<Self> {
+        match event_type {
+            udev::EventType::Add => Some(Self::Added),
+            udev::EventType::Change => Some(Self::Changed),
+            udev::EventType::Remove => Some(Self::Removed),
+            _ => None,
+        }
+    }
+}
+
+/// A single DRM hotplug event, identifying the affected card by its
+/// `dev_t` so callers can match it against an already-open device.
+#[derive(Debug, Clone, Copy)]
+pub struct DrmEvent {
+    pub kind: DrmEventKind,
+    pub devnum: u64,
+}
+
+/// Spawns a task that watches the `drm` subsystem for hotplug events and
+/// forwards them over an unbounded channel, so the caller can fold it
+/// into a `tokio::select!` alongside other event sources.
+pub fn spawn_monitor() -> Result<mpsc::UnboundedReceiver<DrmEvent>> {
+    let socket = udev::MonitorBuilder::new()
+        .context("Failed to create udev monitor")?
+        .match_subsystem("drm")
+        .context("Failed to match drm subsystem")?
+        .listen()
+        .context("Failed to start udev monitor")?;
+
+    let mut async_fd = AsyncFd::new(socket).context("Failed to register udev monitor fd")?;
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        loop {
+            let mut guard = match async_fd.readable_mut().await {
+                Ok(guard) => guard,
+                Err(err) => {
+                    warn!("udev monitor socket error: {err}");
+                    break;
+                }
+            };
+
+            let events: Vec<_> = guard.get_inner().iter().collect();
+            guard.clear_ready();
+
+            for event in events {
+                let (Some(kind), Some(devnum)) =
+                    (DrmEventKind::from_udev(event.event_type()), event.devnum())
+                else {
+                    continue;
+                };
+
+                trace!("udev DRM event: {kind:?} for {devnum}");
+
+                if tx.send(DrmEvent { kind, devnum }).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(rx)
+}